@@ -0,0 +1,136 @@
+//! Directory entries returned from `Readdir`/`Readdirplus`.
+
+use crate::fs::FileAttr;
+use polyfuse_sys::abi::fuse_entry_out;
+use std::{convert::TryFrom, ffi::OsStr, os::unix::ffi::OsStrExt, time::Duration};
+
+/// The type of a directory entry, mirroring the POSIX `d_type` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirEntryType {
+    Fifo,
+    CharDevice,
+    Directory,
+    BlockDevice,
+    Regular,
+    Symlink,
+    Socket,
+    Unknown,
+}
+
+impl DirEntryType {
+    fn as_dtype(self) -> u32 {
+        let dtype = match self {
+            Self::Fifo => libc::DT_FIFO,
+            Self::CharDevice => libc::DT_CHR,
+            Self::Directory => libc::DT_DIR,
+            Self::BlockDevice => libc::DT_BLK,
+            Self::Regular => libc::DT_REG,
+            Self::Symlink => libc::DT_LNK,
+            Self::Socket => libc::DT_SOCK,
+            Self::Unknown => libc::DT_UNKNOWN,
+        };
+        u32::from(dtype)
+    }
+}
+
+#[derive(Debug)]
+struct DirEntryAttr {
+    attr: FileAttr,
+    ttl_entry: Duration,
+    ttl_attr: Duration,
+}
+
+/// A single directory entry.
+#[derive(Debug)]
+pub struct DirEntry {
+    typ: DirEntryType,
+    name: Vec<u8>,
+    ino: u64,
+    off: u64,
+    plus: Option<DirEntryAttr>,
+}
+
+impl DirEntry {
+    fn new(typ: DirEntryType, name: impl AsRef<OsStr>, ino: u64, off: u64) -> Self {
+        Self {
+            typ,
+            name: name.as_ref().as_bytes().to_owned(),
+            ino,
+            off,
+            plus: None,
+        }
+    }
+
+    /// Create an entry for a regular file.
+    pub fn file(name: impl AsRef<OsStr>, ino: u64, off: u64) -> Self {
+        Self::new(DirEntryType::Regular, name, ino, off)
+    }
+
+    /// Create an entry for a directory.
+    pub fn dir(name: impl AsRef<OsStr>, ino: u64, off: u64) -> Self {
+        Self::new(DirEntryType::Directory, name, ino, off)
+    }
+
+    /// Create an entry for a symbolic link.
+    pub fn symlink(name: impl AsRef<OsStr>, ino: u64, off: u64) -> Self {
+        Self::new(DirEntryType::Symlink, name, ino, off)
+    }
+
+    /// Attach the full attributes for this entry, so that a
+    /// `Readdirplus` reply can populate the kernel's dentry and inode
+    /// caches for it in the same round trip.
+    ///
+    /// Sending an entry this way increments the kernel's lookup count
+    /// exactly like a [`Lookup`](crate::Operation::Lookup) reply, so it
+    /// must eventually be balanced by a corresponding
+    /// [`Forget`](crate::Operation::Forget).
+    pub fn attr(mut self, attr: FileAttr, ttl_entry: Duration, ttl_attr: Duration) -> Self {
+        self.plus = Some(DirEntryAttr {
+            attr,
+            ttl_entry,
+            ttl_attr,
+        });
+        self
+    }
+
+    /// Serialize this entry as a plain `fuse_dirent` record: `ino`,
+    /// `off`, `namelen`, `type`, followed by the name and padding to
+    /// 8-byte alignment.
+    pub(crate) fn to_dirent_bytes(&self) -> Vec<u8> {
+        let namelen = u32::try_from(self.name.len()).expect("entry name too long");
+        let mut buf = Vec::with_capacity(24 + self.name.len() + 8);
+        buf.extend_from_slice(&self.ino.to_ne_bytes());
+        buf.extend_from_slice(&self.off.to_ne_bytes());
+        buf.extend_from_slice(&namelen.to_ne_bytes());
+        buf.extend_from_slice(&self.typ.as_dtype().to_ne_bytes());
+        buf.extend_from_slice(&self.name);
+        let padded_len = (buf.len() + 7) & !7;
+        buf.resize(padded_len, 0);
+        buf
+    }
+
+    /// Serialize this entry as a `fuse_direntplus` record: a
+    /// `fuse_entry_out` (using the attributes set via
+    /// [`DirEntry::attr`], or zeroed/unset TTLs if none were set)
+    /// immediately followed by the `fuse_dirent` record from
+    /// [`DirEntry::to_dirent_bytes`].
+    ///
+    /// If [`DirEntry::attr`] was never called, `nodeid` is left `0` so
+    /// the kernel does not instantiate an inode or take a lookup
+    /// reference for an entry with no real attributes to back it.
+    pub(crate) fn to_direntplus_bytes(&self) -> Vec<u8> {
+        let mut entry_out: fuse_entry_out = unsafe { std::mem::zeroed() };
+        if let Some(plus) = &self.plus {
+            entry_out.nodeid = self.ino;
+            entry_out.entry_valid = plus.ttl_entry.as_secs();
+            entry_out.entry_valid_nsec = plus.ttl_entry.subsec_nanos();
+            entry_out.attr_valid = plus.ttl_attr.as_secs();
+            entry_out.attr_valid_nsec = plus.ttl_attr.subsec_nanos();
+            entry_out.attr = *plus.attr.as_raw();
+        }
+
+        let mut buf = entry_out.as_bytes().to_owned();
+        buf.extend(self.to_dirent_bytes());
+        buf
+    }
+}
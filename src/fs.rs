@@ -6,11 +6,15 @@ use crate::reply::{
     ReplyBmap,
     ReplyCreate,
     ReplyData,
+    ReplyDirentplus,
     ReplyEmpty,
     ReplyEntry,
+    ReplyIoctl,
     ReplyLk,
+    ReplyLseek,
     ReplyOpen,
     ReplyOpendir,
+    ReplyPoll,
     ReplyReadlink,
     ReplyStatfs,
     ReplyWrite,
@@ -41,6 +45,10 @@ impl TryFrom<libc::stat> for FileAttr {
 }
 
 impl FileAttr {
+    pub(crate) fn as_raw(&self) -> &fuse_attr {
+        &self.0
+    }
+
     pub(crate) fn into_inner(self) -> fuse_attr {
         self.0
     }
@@ -70,6 +78,54 @@ impl FileLock {
     }
 }
 
+bitflags::bitflags! {
+    /// The mode bits passed to `fallocate(2)`.
+    #[derive(Default)]
+    pub struct FallocateFlags: u32 {
+        /// Do not change the file size, even if the range extends
+        /// beyond the end of the file.
+        const KEEP_SIZE = 0x01;
+
+        /// Deallocate the given range, creating a hole.
+        ///
+        /// Must be combined with `KEEP_SIZE`; filesystems should reject
+        /// a request that sets this flag without it with `EINVAL`.
+        const PUNCH_HOLE = 0x02;
+
+        /// Remove the given range from the file, shifting the
+        /// remaining data to fill the gap and shrinking the file.
+        const COLLAPSE_RANGE = 0x08;
+
+        /// Zero the given range, allocating blocks as needed.
+        const ZERO_RANGE = 0x10;
+
+        /// Insert a hole of the given range, shifting existing data
+        /// to make room and growing the file.
+        const INSERT_RANGE = 0x20;
+    }
+}
+
+/// Set by the kernel in the `flags` of an [`Operation::Ioctl`] request
+/// for an unrestricted ioctl: one whose input and output regions are
+/// not derived from the `_IOC` encoding of `cmd` but are described by
+/// the iovecs the filesystem supplies with [`ReplyIoctl::retry`].
+///
+/// [`ReplyIoctl`] never sets this bit; it is only read from requests.
+pub const FUSE_IOCTL_UNRESTRICTED: u32 = 0x02;
+
+/// Ask the kernel to resolve the iovecs set on [`ReplyIoctl`] and
+/// reissue the ioctl with the requested memory filled in.
+pub const FUSE_IOCTL_RETRY: u32 = 0x04;
+
+/// Tell the filesystem that the kernel wants to be woken up when the
+/// readiness of the polled file changes, via [`Session::notify_poll`].
+///
+/// This flag is set on [`Operation::Poll`] requests that carry a poll
+/// handle (`kh`) worth remembering; it is unset for one-shot polls.
+///
+/// [`Session::notify_poll`]: crate::Session::notify_poll
+pub const FUSE_POLL_SCHEDULE_NOTIFY: u32 = 0x01;
+
 /// The filesystem running on the user space.
 #[async_trait::async_trait(?Send)]
 pub trait Filesystem<T> {
@@ -160,6 +216,12 @@ pub enum Operation<'a, T> {
     },
 
     /// Forget about inodes removed from the kernel's internal caches.
+    ///
+    /// The kernel may coalesce evictions of multiple inodes into a
+    /// single `FUSE_BATCH_FORGET` request once batching has been
+    /// negotiated (see `CapabilityFlags::BATCH_FORGET`); such requests
+    /// are decoded into the same `nlookups` slice as ordinary
+    /// single-inode forgets.
     Forget {
         nlookups: &'a [(u64, u64)], //
     },
@@ -366,6 +428,22 @@ pub enum Operation<'a, T> {
         reply: ReplyData,
     },
 
+    /// Read contents from an opened directory, together with the
+    /// attributes of each entry.
+    ///
+    /// Each returned entry that carries attributes (see
+    /// [`DirEntry::attr`](crate::DirEntry::attr)) increments the kernel's
+    /// lookup count exactly like [`Operation::Lookup`], so these entries
+    /// must eventually be balanced by a corresponding
+    /// [`Operation::Forget`]. Entries without attributes are sent with a
+    /// `nodeid` of `0` and take no lookup reference.
+    Readdirplus {
+        ino: u64,
+        fh: u64,
+        offset: u64,
+        reply: ReplyDirentplus,
+    },
+
     /// Release an opened directory.
     Releasedir {
         ino: u64,
@@ -442,13 +520,84 @@ pub enum Operation<'a, T> {
         blocksize: u32,
         reply: ReplyBmap,
     },
-    // ioctl
-    // poll
+
+    /// Copy a range of data from one opened file to another, without
+    /// copying through userspace.
+    CopyFileRange {
+        ino_in: u64,
+        fh_in: u64,
+        offset_in: u64,
+        ino_out: u64,
+        fh_out: u64,
+        offset_out: u64,
+        len: u64,
+        flags: u64,
+        reply: ReplyWrite,
+    },
+
+    /// Find the next data or hole region in a sparse file.
+    ///
+    /// `whence` is one of the standard `SEEK_*` constants, most notably
+    /// `SEEK_DATA` and `SEEK_HOLE`. Filesystems that do not track holes
+    /// should reply with `ENOSYS` so the kernel falls back to the default
+    /// behavior.
+    Lseek {
+        ino: u64,
+        fh: u64,
+        offset: u64,
+        whence: u32,
+        reply: ReplyLseek,
+    },
+
+    /// Allocate, zero or deallocate a range of an opened file.
+    Fallocate {
+        ino: u64,
+        fh: u64,
+        offset: u64,
+        length: u64,
+        mode: FallocateFlags,
+        reply: ReplyEmpty,
+    },
+
+    /// Handle an ioctl on an opened file.
+    ///
+    /// `in_data` carries the fixed-size input buffer the kernel has
+    /// already copied in for a *restricted* ioctl (its size is derived
+    /// from the `_IOC` encoding of `cmd`). For an *unrestricted* ioctl
+    /// (see [`FUSE_IOCTL_UNRESTRICTED`]) it holds the `in_size` bytes the
+    /// kernel gathered from the iovecs instead.
+    ///
+    /// The reply is either a final result with up to `out_size` bytes
+    /// of output data, or a retry request carrying the input/output
+    /// iovecs the kernel must resolve before reissuing the ioctl with
+    /// the requested memory filled in.
+    Ioctl {
+        ino: u64,
+        fh: u64,
+        flags: u32,
+        cmd: u32,
+        arg: u64,
+        in_data: &'a [u8],
+        out_size: u32,
+        reply: ReplyIoctl,
+    },
+
+    /// Poll a file for I/O readiness.
+    ///
+    /// When the request flags include [`FUSE_POLL_SCHEDULE_NOTIFY`], the
+    /// filesystem should remember `kh` and, once readiness changes,
+    /// call [`Session::notify_poll`] with it so the kernel re-polls the
+    /// file instead of waiting for the next explicit poll.
+    ///
+    /// [`Session::notify_poll`]: crate::Session::notify_poll
+    Poll {
+        ino: u64,
+        fh: u64,
+        kh: u64,
+        events: u32,
+        flags: u32,
+        reply: ReplyPoll,
+    },
     // notify_reply
-    // batch_forget
-    // fallocate
-    // readdirplus
     // rename2
-    // lseek
-    // copy_file_range
 }
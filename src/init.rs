@@ -0,0 +1,17 @@
+//! Capability negotiation with the kernel at mount time.
+
+bitflags::bitflags! {
+    /// Optional protocol features the kernel and filesystem can agree to
+    /// use, negotiated during `FUSE_INIT`.
+    #[derive(Default)]
+    pub struct CapabilityFlags: u32 {
+        /// The kernel may coalesce evictions of multiple inodes into a
+        /// single `FUSE_BATCH_FORGET` request instead of sending one
+        /// `FUSE_FORGET` per inode.
+        ///
+        /// Requests decoded this way still surface as an ordinary
+        /// [`Operation::Forget`](crate::Operation::Forget), with
+        /// `nlookups` covering every inode in the batch.
+        const BATCH_FORGET = 0x0200;
+    }
+}
@@ -25,7 +25,7 @@ mod session;
 pub use crate::{
     common::{FileAttr, FileLock, Forget, FsStatistics},
     dirent::{DirEntry, DirEntryType},
-    fs::{Context, Filesystem, Operation},
+    fs::{Context, FallocateFlags, Filesystem, Operation},
     init::{CapabilityFlags, ConnectionInfo, SessionInitializer},
     request::Buffer,
     session::{Interrupt, NotifyRetrieve, Session},
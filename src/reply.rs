@@ -0,0 +1,411 @@
+//! Reply builders for FUSE requests.
+
+use crate::{
+    dirent::DirEntry,
+    fs::{FileAttr, FileLock, FsStatistics, FUSE_IOCTL_RETRY},
+};
+use polyfuse_sys::abi::{
+    fuse_attr_out, //
+    fuse_bmap_out,
+    fuse_create_out,
+    fuse_entry_out,
+    fuse_getxattr_out,
+    fuse_ioctl_iovec,
+    fuse_ioctl_out,
+    fuse_lk_out,
+    fuse_lseek_out,
+    fuse_open_out,
+    fuse_poll_out,
+    fuse_statfs_out,
+    fuse_write_out,
+};
+use std::{convert::TryFrom, time::Duration};
+
+/// A value that can be serialized as (part of) a reply payload.
+pub(crate) trait Payload {
+    fn as_bytes(&self) -> &[u8];
+}
+
+macro_rules! impl_payload_for_abi {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl Payload for $ty {
+                fn as_bytes(&self) -> &[u8] {
+                    <$ty>::as_bytes(self)
+                }
+            }
+        )*
+    };
+}
+
+impl_payload_for_abi! {
+    fuse_attr_out,
+    fuse_entry_out,
+    fuse_open_out,
+    fuse_write_out,
+    fuse_statfs_out,
+    fuse_getxattr_out,
+    fuse_lk_out,
+    fuse_bmap_out,
+    fuse_create_out,
+    fuse_ioctl_out,
+    fuse_ioctl_iovec,
+}
+
+fn secs_and_nanos(ttl: Duration) -> (u64, u32) {
+    (ttl.as_secs(), ttl.subsec_nanos())
+}
+
+/// A reply that carries no data of its own.
+#[derive(Debug, Default)]
+pub struct ReplyEmpty;
+
+/// A reply to a `Getattr`/`Setattr` request.
+#[derive(Debug)]
+pub struct ReplyAttr(fuse_attr_out);
+
+impl ReplyAttr {
+    pub fn new(attr: FileAttr) -> Self {
+        let mut out: fuse_attr_out = unsafe { std::mem::zeroed() };
+        out.attr = *attr.as_raw();
+        Self(out)
+    }
+
+    pub fn ttl_attr(mut self, ttl: Duration) -> Self {
+        let (secs, nanos) = secs_and_nanos(ttl);
+        self.0.attr_valid = secs;
+        self.0.attr_valid_nsec = nanos;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_attr_out {
+        self.0
+    }
+}
+
+/// A reply that introduces a new directory entry into the kernel's caches.
+#[derive(Debug, Default)]
+pub struct ReplyEntry(fuse_entry_out);
+
+impl ReplyEntry {
+    pub fn ino(mut self, ino: u64) -> Self {
+        self.0.nodeid = ino;
+        self
+    }
+
+    pub fn generation(mut self, generation: u64) -> Self {
+        self.0.generation = generation;
+        self
+    }
+
+    pub fn attr(mut self, attr: FileAttr) -> Self {
+        self.0.attr = *attr.as_raw();
+        self
+    }
+
+    pub fn ttl_entry(mut self, ttl: Duration) -> Self {
+        let (secs, nanos) = secs_and_nanos(ttl);
+        self.0.entry_valid = secs;
+        self.0.entry_valid_nsec = nanos;
+        self
+    }
+
+    pub fn ttl_attr(mut self, ttl: Duration) -> Self {
+        let (secs, nanos) = secs_and_nanos(ttl);
+        self.0.attr_valid = secs;
+        self.0.attr_valid_nsec = nanos;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_entry_out {
+        self.0
+    }
+}
+
+/// A reply to an `Open`/`Create` request.
+#[derive(Debug, Default)]
+pub struct ReplyOpen(fuse_open_out);
+
+impl ReplyOpen {
+    pub fn fh(mut self, fh: u64) -> Self {
+        self.0.fh = fh;
+        self
+    }
+
+    pub fn flags(mut self, flags: u32) -> Self {
+        self.0.open_flags = flags;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_open_out {
+        self.0
+    }
+}
+
+/// A reply to an `Opendir` request.
+pub type ReplyOpendir = ReplyOpen;
+
+/// A reply carrying raw file or directory data.
+#[derive(Debug, Default)]
+pub struct ReplyData(Vec<u8>);
+
+impl ReplyData {
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for ReplyData {
+    fn from(data: Vec<u8>) -> Self {
+        Self(data)
+    }
+}
+
+/// A reply to a `Write`/`CopyFileRange` request.
+#[derive(Debug, Default)]
+pub struct ReplyWrite(fuse_write_out);
+
+impl ReplyWrite {
+    pub fn size(mut self, size: u32) -> Self {
+        self.0.size = size;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_write_out {
+        self.0
+    }
+}
+
+/// A reply to a `Statfs` request.
+#[derive(Debug, Default)]
+pub struct ReplyStatfs(fuse_statfs_out);
+
+impl ReplyStatfs {
+    pub fn statfs(mut self, statfs: FsStatistics) -> Self {
+        self.0.st = statfs.into_inner();
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_statfs_out {
+        self.0
+    }
+}
+
+/// A reply to a `Getxattr`/`Listxattr` request.
+#[derive(Debug, Default)]
+pub struct ReplyXattr(ReplyXattrInner);
+
+#[derive(Debug)]
+enum ReplyXattrInner {
+    Size(fuse_getxattr_out),
+    Data(Vec<u8>),
+}
+
+impl Default for ReplyXattrInner {
+    fn default() -> Self {
+        Self::Data(Vec::new())
+    }
+}
+
+impl ReplyXattr {
+    pub fn size(self, size: u32) -> Self {
+        let mut out: fuse_getxattr_out = unsafe { std::mem::zeroed() };
+        out.size = size;
+        Self(ReplyXattrInner::Size(out))
+    }
+
+    pub fn data(self, data: impl Into<Vec<u8>>) -> Self {
+        Self(ReplyXattrInner::Data(data.into()))
+    }
+}
+
+/// A reply to a `Getlk` request.
+#[derive(Debug, Default)]
+pub struct ReplyLk(fuse_lk_out);
+
+impl ReplyLk {
+    pub fn lk(mut self, lk: &FileLock) -> Self {
+        self.0.lk = lk.into_inner();
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_lk_out {
+        self.0
+    }
+}
+
+/// A reply to a `Create` request, combining an entry and an open handle.
+#[derive(Debug, Default)]
+pub struct ReplyCreate(fuse_create_out);
+
+impl ReplyCreate {
+    pub fn entry(mut self, entry: ReplyEntry) -> Self {
+        self.0.entry_out = entry.into_inner();
+        self
+    }
+
+    pub fn open(mut self, open: ReplyOpen) -> Self {
+        self.0.open_out = open.into_inner();
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_create_out {
+        self.0
+    }
+}
+
+/// A reply to a `Bmap` request.
+#[derive(Debug, Default)]
+pub struct ReplyBmap(fuse_bmap_out);
+
+impl ReplyBmap {
+    pub fn block(mut self, block: u64) -> Self {
+        self.0.block = block;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_bmap_out {
+        self.0
+    }
+}
+
+/// A reply to a `Readlink` request.
+#[derive(Debug, Default)]
+pub struct ReplyReadlink(Vec<u8>);
+
+impl ReplyReadlink {
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl From<Vec<u8>> for ReplyReadlink {
+    fn from(data: Vec<u8>) -> Self {
+        Self(data)
+    }
+}
+
+/// A reply to an `Lseek` request, carrying the resolved offset of the
+/// next data or hole region.
+#[derive(Debug, Default)]
+pub struct ReplyLseek(fuse_lseek_out);
+
+impl ReplyLseek {
+    /// Set the offset found by `SEEK_DATA`/`SEEK_HOLE`.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.0.offset = offset;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_lseek_out {
+        self.0
+    }
+}
+
+/// A reply to a `Readdirplus` request, serializing a sequence of
+/// `fuse_direntplus` records.
+#[derive(Debug, Default)]
+pub struct ReplyDirentplus(Vec<u8>);
+
+impl ReplyDirentplus {
+    /// Append one directory entry.
+    ///
+    /// Each entry with attributes set via [`DirEntry::attr`] increments
+    /// the kernel's lookup count exactly like a `Lookup` reply, so it must
+    /// eventually be balanced by a `Forget`. An entry without attributes
+    /// is sent with a `nodeid` of `0` and takes no lookup reference.
+    pub fn entry(mut self, entry: DirEntry) -> Self {
+        self.0.extend(entry.to_direntplus_bytes());
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+#[derive(Debug, Default)]
+struct RetryIovecs {
+    in_iovs: Vec<(u64, u64)>,
+    out_iovs: Vec<(u64, u64)>,
+}
+
+/// A reply to an `Ioctl` request.
+///
+/// Either a final result carrying up to `out_size` bytes of output
+/// data ([`ReplyIoctl::result`]/[`ReplyIoctl::data`]), or — when the
+/// ioctl needs the two-phase retry protocol — a request for the kernel
+/// to resolve the given input/output iovecs and reissue the ioctl with
+/// them filled in ([`ReplyIoctl::retry`]).
+#[derive(Debug, Default)]
+pub struct ReplyIoctl {
+    result: i32,
+    data: Vec<u8>,
+    retry: Option<RetryIovecs>,
+}
+
+impl ReplyIoctl {
+    /// Set the ioctl's integer result.
+    pub fn result(mut self, result: i32) -> Self {
+        self.result = result;
+        self
+    }
+
+    /// Set the output data to copy back to the caller's buffer.
+    pub fn data(mut self, data: impl Into<Vec<u8>>) -> Self {
+        self.data = data.into();
+        self
+    }
+
+    /// Request the `FUSE_IOCTL_RETRY` two-phase protocol: the kernel
+    /// reads `in_iovs` and writes `out_iovs` (each a `(base, len)`
+    /// user-memory region), then reissues the ioctl with that data
+    /// filled in.
+    pub fn retry(mut self, in_iovs: Vec<(u64, u64)>, out_iovs: Vec<(u64, u64)>) -> Self {
+        self.retry = Some(RetryIovecs { in_iovs, out_iovs });
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> (fuse_ioctl_out, Vec<u8>) {
+        match self.retry {
+            Some(retry) => {
+                let out = fuse_ioctl_out {
+                    result: self.result,
+                    flags: FUSE_IOCTL_RETRY,
+                    in_iovs: u32::try_from(retry.in_iovs.len()).expect("too many input iovecs"),
+                    out_iovs: u32::try_from(retry.out_iovs.len()).expect("too many output iovecs"),
+                };
+                let mut data = Vec::new();
+                for (base, len) in retry.in_iovs.into_iter().chain(retry.out_iovs) {
+                    data.extend_from_slice(fuse_ioctl_iovec { base, len }.as_bytes());
+                }
+                (out, data)
+            }
+            None => {
+                let out = fuse_ioctl_out {
+                    result: self.result,
+                    flags: 0,
+                    in_iovs: 0,
+                    out_iovs: 0,
+                };
+                (out, self.data)
+            }
+        }
+    }
+}
+
+/// A reply to a `Poll` request.
+#[derive(Debug, Default)]
+pub struct ReplyPoll(fuse_poll_out);
+
+impl ReplyPoll {
+    /// Set the file's current readiness, as a mask of `POLL*` bits.
+    pub fn revents(mut self, revents: u32) -> Self {
+        self.0.revents = revents;
+        self
+    }
+
+    pub(crate) fn into_inner(self) -> fuse_poll_out {
+        self.0
+    }
+}
@@ -0,0 +1,249 @@
+//! Decoding of incoming FUSE requests into [`Operation`](crate::fs::Operation) values.
+
+use crate::{
+    fs::{FallocateFlags, Operation},
+    reply::{ReplyDirentplus, ReplyEmpty, ReplyIoctl, ReplyLseek, ReplyPoll, ReplyWrite},
+};
+use polyfuse_sys::abi::{
+    fuse_batch_forget_in, //
+    fuse_copy_file_range_in,
+    fuse_fallocate_in,
+    fuse_forget_one,
+    fuse_in_header,
+    fuse_ioctl_in,
+    fuse_lseek_in,
+    fuse_poll_in,
+    fuse_read_in,
+};
+use std::{convert::TryFrom, io, mem};
+
+/// Opcodes for the operations decoded by this module.
+///
+/// The remaining `FUSE_*` opcodes are dispatched by the rest of the
+/// request layer; only the ones this module decodes are listed here.
+const FUSE_IOCTL: u32 = 39;
+const FUSE_POLL: u32 = 40;
+const FUSE_BATCH_FORGET: u32 = 42;
+const FUSE_FALLOCATE: u32 = 43;
+const FUSE_READDIRPLUS: u32 = 44;
+const FUSE_LSEEK: u32 = 46;
+const FUSE_COPY_FILE_RANGE: u32 = 47;
+
+/// Read a `Copy`, `repr(C)` value from the front of `buf`, returning the
+/// value together with the remaining bytes.
+fn take<T: Copy>(buf: &[u8]) -> io::Result<(T, &[u8])> {
+    let size = mem::size_of::<T>();
+    if buf.len() < size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "request body is shorter than the expected struct",
+        ));
+    }
+    let (head, rest) = buf.split_at(size);
+    // SAFETY: `head` is exactly `size_of::<T>()` bytes and `T` is one of
+    // the plain-old-data FUSE ABI structs this function is used with, so
+    // any bit pattern the kernel sends is a valid `T`.
+    let value = unsafe { std::ptr::read_unaligned(head.as_ptr() as *const T) };
+    Ok((value, rest))
+}
+
+/// Decode the body of a `FUSE_COPY_FILE_RANGE` request.
+///
+/// The source inode is the request header's `nodeid`; the destination
+/// inode travels inside the struct as `nodeid_out`.
+pub(crate) fn decode_copy_file_range<'a, T>(
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, _): (fuse_copy_file_range_in, _) = take(arg)?;
+    Ok(Operation::CopyFileRange {
+        ino_in: header.nodeid,
+        fh_in: raw.fh_in,
+        offset_in: raw.off_in,
+        ino_out: raw.nodeid_out,
+        fh_out: raw.fh_out,
+        offset_out: raw.off_out,
+        len: raw.len,
+        flags: raw.flags,
+        reply: ReplyWrite::default(),
+    })
+}
+
+/// Decode the body of a `FUSE_LSEEK` request.
+///
+/// `whence` is one of the standard `SEEK_*` constants; `SEEK_DATA` (3)
+/// and `SEEK_HOLE` (4) are the ones filesystems care about for sparse
+/// file support.
+pub(crate) fn decode_lseek<'a, T>(
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, _): (fuse_lseek_in, _) = take(arg)?;
+    Ok(Operation::Lseek {
+        ino: header.nodeid,
+        fh: raw.fh,
+        offset: raw.offset,
+        whence: raw.whence,
+        reply: ReplyLseek::default(),
+    })
+}
+
+/// Decode the body of a `FUSE_FALLOCATE` request.
+///
+/// `mode` may legally combine `PUNCH_HOLE` without `KEEP_SIZE`
+/// according to the kernel's encoding, but filesystems should reject
+/// that combination with `EINVAL` (see
+/// [`FallocateFlags::PUNCH_HOLE`](crate::FallocateFlags::PUNCH_HOLE));
+/// that decision is left to the filesystem rather than enforced here.
+pub(crate) fn decode_fallocate<'a, T>(
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, _): (fuse_fallocate_in, _) = take(arg)?;
+    let mode = FallocateFlags::from_bits_truncate(raw.mode);
+    Ok(Operation::Fallocate {
+        ino: header.nodeid,
+        fh: raw.fh,
+        offset: raw.offset,
+        length: raw.length,
+        mode,
+        reply: ReplyEmpty::default(),
+    })
+}
+
+/// Decode the body of a `FUSE_READDIRPLUS` request.
+///
+/// The request body has the same shape as a plain `FUSE_READDIR`
+/// request (`fuse_read_in`); the two opcodes differ only in what the
+/// filesystem is expected to reply with.
+pub(crate) fn decode_readdirplus<'a, T>(
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, _): (fuse_read_in, _) = take(arg)?;
+    Ok(Operation::Readdirplus {
+        ino: header.nodeid,
+        fh: raw.fh,
+        offset: raw.offset,
+        reply: ReplyDirentplus::default(),
+    })
+}
+
+/// Decode the body of a `FUSE_IOCTL` request.
+///
+/// For a *restricted* ioctl the kernel has already copied in a
+/// fixed-size buffer of `in_size` bytes (derived from the `_IOC`
+/// encoding of `cmd`), which follows `fuse_ioctl_in` in `arg`. For an
+/// *unrestricted* ioctl (`flags & FUSE_IOCTL_UNRESTRICTED`), `in_size`
+/// is the size of the iovec-described buffer the kernel has gathered
+/// instead, and `in_data` should be interpreted the same way.
+pub(crate) fn decode_ioctl<'a, T>(
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, rest): (fuse_ioctl_in, _) = take(arg)?;
+    let in_size =
+        usize::try_from(raw.in_size).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if rest.len() < in_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "ioctl input buffer is shorter than in_size",
+        ));
+    }
+    Ok(Operation::Ioctl {
+        ino: header.nodeid,
+        fh: raw.fh,
+        flags: raw.flags,
+        cmd: raw.cmd,
+        arg: raw.arg,
+        in_data: &rest[..in_size],
+        out_size: raw.out_size,
+        reply: ReplyIoctl::default(),
+    })
+}
+
+/// Decode the body of a `FUSE_POLL` request.
+///
+/// When `flags` includes `FUSE_POLL_SCHEDULE_NOTIFY`, the filesystem
+/// should remember `kh` and later call
+/// [`Session::notify_poll`](crate::Session::notify_poll) with it once
+/// the file's readiness changes.
+pub(crate) fn decode_poll<'a, T>(
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, _): (fuse_poll_in, _) = take(arg)?;
+    Ok(Operation::Poll {
+        ino: header.nodeid,
+        fh: raw.fh,
+        kh: raw.kh,
+        events: raw.events,
+        flags: raw.flags,
+        reply: ReplyPoll::default(),
+    })
+}
+
+/// Decode the body of a `FUSE_BATCH_FORGET` request.
+///
+/// The body is a `fuse_batch_forget_in` header giving the number of
+/// entries, followed by that many `fuse_forget_one { nodeid, nlookup }`
+/// records. `fuse_forget_one` has the same layout as `(u64, u64)`, so
+/// the trailing bytes are reinterpreted in place rather than copied,
+/// exactly like the `nlookups` slice of an ordinary single-inode
+/// `Operation::Forget`.
+pub(crate) fn decode_batch_forget<'a, T>(
+    _header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Operation<'a, T>> {
+    let (raw, rest): (fuse_batch_forget_in, _) = take(arg)?;
+    let count =
+        usize::try_from(raw.count).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let entry_size = mem::size_of::<fuse_forget_one>();
+    let needed = count.checked_mul(entry_size).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "batch forget entry count overflows",
+        )
+    })?;
+    if rest.len() < needed {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "batch forget body is shorter than its entry count",
+        ));
+    }
+    // `fuse_forget_one` has the same layout as `(u64, u64)`, but unlike
+    // `take()` this reinterprets the buffer in place instead of reading
+    // through it, so the alignment `take()` sidesteps via
+    // `read_unaligned` has to be checked explicitly here.
+    if (rest.as_ptr() as usize) % mem::align_of::<(u64, u64)>() != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "batch forget body is not aligned for in-place reinterpretation",
+        ));
+    }
+    // SAFETY: `rest` has just been checked to be aligned for `(u64, u64)`
+    // and to hold at least `count` of them.
+    let nlookups = unsafe { std::slice::from_raw_parts(rest.as_ptr().cast(), count) };
+    Ok(Operation::Forget { nlookups })
+}
+
+/// Route a decoded opcode to the appropriate `decode_*` function.
+///
+/// Returns `None` for opcodes this module does not (yet) handle, which
+/// the rest of the request layer's dispatch table is responsible for.
+pub(crate) fn decode<'a, T>(
+    opcode: u32,
+    header: &fuse_in_header,
+    arg: &'a [u8],
+) -> io::Result<Option<Operation<'a, T>>> {
+    match opcode {
+        FUSE_IOCTL => decode_ioctl(header, arg).map(Some),
+        FUSE_POLL => decode_poll(header, arg).map(Some),
+        FUSE_BATCH_FORGET => decode_batch_forget(header, arg).map(Some),
+        FUSE_FALLOCATE => decode_fallocate(header, arg).map(Some),
+        FUSE_READDIRPLUS => decode_readdirplus(header, arg).map(Some),
+        FUSE_LSEEK => decode_lseek(header, arg).map(Some),
+        FUSE_COPY_FILE_RANGE => decode_copy_file_range(header, arg).map(Some),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,48 @@
+//! The running FUSE session and its kernel notification channel.
+
+use futures_io::AsyncWrite;
+use futures_util::io::AsyncWriteExt;
+use polyfuse_sys::abi::{fuse_notify_poll_wakeup_out, fuse_out_header};
+use std::{convert::TryFrom, io, io::IoSlice, mem};
+
+/// `fuse_notify_code::FUSE_NOTIFY_POLL`.
+const FUSE_NOTIFY_POLL: i32 = 1;
+
+/// A handle to the running FUSE session, used to send notifications to
+/// the kernel outside of the ordinary request/reply cycle.
+#[derive(Debug)]
+pub struct Session<W> {
+    writer: W,
+}
+
+impl<W> Session<W>
+where
+    W: AsyncWrite + Unpin,
+{
+    pub(crate) fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Tell the kernel that the file identified by `kh` is ready to be
+    /// re-polled.
+    ///
+    /// `kh` is the poll handle recorded from an
+    /// [`Operation::Poll`](crate::Operation::Poll) request whose flags
+    /// included `FUSE_POLL_SCHEDULE_NOTIFY`; calling this wakes up
+    /// whatever is blocked on `poll(2)`/`select(2)` against that file.
+    pub async fn notify_poll(&mut self, kh: u64) -> io::Result<()> {
+        let wakeup = fuse_notify_poll_wakeup_out { kh };
+        let data = wakeup.as_bytes();
+        let out_header = fuse_out_header {
+            unique: 0,
+            error: FUSE_NOTIFY_POLL,
+            len: u32::try_from(mem::size_of::<fuse_out_header>() + data.len())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        };
+
+        let bufs = [IoSlice::new(out_header.as_bytes()), IoSlice::new(data)];
+        self.writer.write_vectored(&bufs).await?;
+
+        Ok(())
+    }
+}